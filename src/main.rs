@@ -1,11 +1,13 @@
 use axum::{
     body::{Body, HttpBody},
     extract::Query,
+    http::HeaderValue,
     response::Response,
     routing::get,
     Extension, Router,
 };
-use chrono::{Duration, TimeDelta};
+use axum_server::tls_rustls::RustlsConfig;
+use chrono::{Duration, TimeDelta, Utc};
 use dotenv::dotenv;
 use image::{io::Reader as ImageReader, DynamicImage};
 use lambda_http::{
@@ -16,12 +18,20 @@ use lambda_http::{
 use once_cell::sync::Lazy;
 use rspotify::{
     clients::{BaseClient, OAuthClient},
-    model::{AdditionalType, Device, FullTrack, RepeatState},
+    model::{AdditionalType, Device, FullEpisode, FullTrack, Id, PlaylistId, RepeatState},
     scopes, AuthCodeSpotify, Credentials, Token,
 };
 use serde::{Deserialize, Serialize};
-use std::{io::Cursor, sync::Arc};
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration as StdDuration, Instant},
+};
 use tokio::sync::Mutex;
+use tower_http::cors::CorsLayer;
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 struct AuthQueryParam {
@@ -35,17 +45,47 @@ struct ImageQueryParam {
     height: u32,
 }
 
+#[derive(Debug, Deserialize)]
+struct PlaylistQueryParam {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetVolumeQueryParam {
+    auth_token: String,
+    volume: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct SeekQueryParam {
+    auth_token: String,
+    position_secs: u32,
+}
+
 #[derive(Debug, Clone)]
 struct PlaybackState {
     is_playing: bool,
     position: u64,
     device_id: String,
+    shuffled: bool,
+    repeat_status: RepeatState,
+}
+
+// A fully-built response plus the instant it was fetched, so `get_current_playback` can serve
+// repeated polls from memory and just interpolate the progress bar in between real fetches.
+#[derive(Debug, Clone)]
+struct PlaybackCache {
+    response: CurrentlyPlaying,
+    last_fetched: Instant,
 }
 
 #[derive(Debug, Clone)]
 struct SpotifyState {
     spotify: AuthCodeSpotify,
     playback_status: Option<PlaybackState>,
+    playback_cache: Option<PlaybackCache>,
+    track_cache: HashMap<String, Track>,
+    episode_cache: HashMap<String, Episode>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -63,10 +103,36 @@ struct Track {
     duration: u32,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct Episode {
+    name: String,
+    show_name: String,
+    image_url: Option<String>,
+    url: Option<String>,
+    duration: u32,
+    resume_position: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PlayableItem {
+    Track(Track),
+    Episode(Episode),
+}
+
+impl PlayableItem {
+    fn duration(&self) -> u32 {
+        match self {
+            PlayableItem::Track(track) => track.duration,
+            PlayableItem::Episode(episode) => episode.duration,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct CurrentlyPlaying {
     device: Device,
-    track: Track,
+    item: PlayableItem,
     progress_secs: u32,
     shuffled: bool,
     playing: bool,
@@ -92,14 +158,92 @@ impl Track {
     }
 }
 
+impl Episode {
+    async fn simplify_episode(full_episode: FullEpisode) -> Self {
+        Self {
+            name: full_episode.name,
+            show_name: full_episode.show.name,
+            image_url: full_episode.images.first().map(|image| image.url.clone()),
+            url: full_episode.external_urls.get("spotify").cloned(),
+            duration: full_episode.duration.num_seconds() as u32,
+            resume_position: full_episode
+                .resume_point
+                .map(|resume_point| resume_point.resume_position.num_seconds() as u32)
+                .unwrap_or(0),
+        }
+    }
+}
+
 // Usually, I would implement an OAuth system with access tokens to make it more secure, but this
 // is so small scale that I'm fine with having a token I can just recreate if need be.
 static AUTH_TOKEN: Lazy<Arc<Mutex<String>>> =
     Lazy::new(|| Arc::new(Mutex::new(dotenv::var("AUTH_TOKEN").unwrap())));
 
+// Refresh a bit before the token actually expires so a request in flight doesn't race the clock.
+const TOKEN_REFRESH_SKEW: Duration = Duration::seconds(60);
+
+// How long a cached `/current_playback` response is served before we hit the Spotify API again.
+static PLAYBACK_CACHE_TTL: Lazy<StdDuration> = Lazy::new(|| {
+    let secs = dotenv::var("PLAYBACK_CACHE_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(5);
+    StdDuration::from_secs(secs)
+});
+
+// Short-lived tokens handed out by `/issue_token`, keyed by token value, valued by expiry. Lets a
+// browser client be given a credential that expires on its own instead of the permanent
+// `AUTH_TOKEN`.
+static SCOPED_TOKENS: Lazy<Arc<Mutex<HashMap<String, Instant>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+static SCOPED_TOKEN_TTL: Lazy<StdDuration> = Lazy::new(|| {
+    let secs = dotenv::var("SCOPED_EXPIRY_DURATION")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(300);
+    StdDuration::from_secs(secs)
+});
+
+// Accepts the master `AUTH_TOKEN` or a still-valid scoped token, sweeping out expired scoped
+// tokens as a side effect so the store doesn't grow unbounded.
+async fn is_authorized(auth_token: &str) -> bool {
+    if AUTH_TOKEN.lock().await.as_str() == auth_token {
+        return true;
+    }
+
+    let mut scoped_tokens = SCOPED_TOKENS.lock().await;
+    let now = Instant::now();
+    scoped_tokens.retain(|_, expires_at| *expires_at > now);
+
+    scoped_tokens
+        .get(auth_token)
+        .is_some_and(|expires_at| *expires_at > now)
+}
+
+// Keeps the access token valid across the lifetime of a long-running instance. rspotify only
+// refreshes when asked, so without this every handler would start 401ing ~1 hour after boot.
+async fn ensure_fresh_token(spotify: &AuthCodeSpotify) {
+    let needs_refresh = {
+        let token_guard = spotify.token.lock().await.unwrap();
+        match token_guard.as_ref().and_then(|token| token.expires_at) {
+            Some(expires_at) => Utc::now() + TOKEN_REFRESH_SKEW >= expires_at,
+            None => true,
+        }
+    };
+
+    if needs_refresh {
+        if let Err(err) = spotify.refresh_token().await {
+            error!("failed to refresh spotify token: {}", err);
+        }
+    }
+}
+
 async fn update_state(state: Extension<Arc<Mutex<SpotifyState>>>) {
     let mut locked_state = state.lock().await;
 
+    ensure_fresh_token(&locked_state.spotify).await;
+
     let currently_playing_res = locked_state
         .spotify
         .current_playback(
@@ -114,6 +258,8 @@ async fn update_state(state: Extension<Arc<Mutex<SpotifyState>>>) {
                 is_playing: playing.is_playing,
                 position: playing.progress.unwrap().num_seconds() as u64,
                 device_id: playing.device.clone().id.unwrap(),
+                shuffled: playing.shuffle_state,
+                repeat_status: playing.repeat_state,
             });
         }
         Ok(None) => {
@@ -125,12 +271,37 @@ async fn update_state(state: Extension<Arc<Mutex<SpotifyState>>>) {
     }
 }
 
+// Advances a cached response's progress locally instead of re-fetching, clamped to the length of
+// the playing track or episode.
+fn interpolate_progress(cache: &PlaybackCache) -> u32 {
+    if !cache.response.playing {
+        return cache.response.progress_secs;
+    }
+
+    let elapsed = cache.last_fetched.elapsed().as_secs() as u32;
+    (cache.response.progress_secs + elapsed).min(cache.response.item.duration())
+}
+
 async fn get_current_playback(
     state: Extension<Arc<Mutex<SpotifyState>>>,
 ) -> Result<Response<Body>, String> {
     let mut locked_state = state.lock().await;
 
-    // TODO: In attempts to not call spotify api as often, make it so it only updates every 5 seconds
+    ensure_fresh_token(&locked_state.spotify).await;
+
+    if let Some(cache) = locked_state.playback_cache.clone() {
+        if cache.last_fetched.elapsed() < *PLAYBACK_CACHE_TTL {
+            let mut response = cache.response.clone();
+            response.progress_secs = interpolate_progress(&cache);
+
+            let body = serde_json::to_string(&response).unwrap();
+
+            return Ok(Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(body))
+                .unwrap());
+        }
+    }
 
     let currently_playing_res = locked_state
         .spotify
@@ -142,15 +313,47 @@ async fn get_current_playback(
 
     match currently_playing_res {
         Ok(Some(playing)) => {
-            let track_info = match playing.item.unwrap().id().unwrap() {
-                rspotify::model::PlayableId::Track(track_id) => locked_state
-                    .spotify
-                    .track(track_id, None)
-                    .await
-                    .expect("Could not get information for track"),
-
-                rspotify::model::PlayableId::Episode(_) => {
-                    unreachable!("Does not parse episodes");
+            let item_info = match playing.item.unwrap().id().unwrap() {
+                rspotify::model::PlayableId::Track(track_id) => {
+                    let cache_key = track_id.id().to_string();
+
+                    let track = if let Some(cached_track) = locked_state.track_cache.get(&cache_key) {
+                        cached_track.clone()
+                    } else {
+                        let full_track = locked_state
+                            .spotify
+                            .track(track_id, None)
+                            .await
+                            .expect("Could not get information for track");
+                        let track = Track::simplify_track(full_track).await;
+                        locked_state.track_cache.insert(cache_key, track.clone());
+                        track
+                    };
+
+                    PlayableItem::Track(track)
+                }
+
+                rspotify::model::PlayableId::Episode(episode_id) => {
+                    let cache_key = episode_id.id().to_string();
+
+                    let episode = if let Some(cached_episode) =
+                        locked_state.episode_cache.get(&cache_key)
+                    {
+                        cached_episode.clone()
+                    } else {
+                        let full_episode = locked_state
+                            .spotify
+                            .get_an_episode(episode_id, None)
+                            .await
+                            .expect("Could not get information for episode");
+                        let episode = Episode::simplify_episode(full_episode).await;
+                        locked_state
+                            .episode_cache
+                            .insert(cache_key, episode.clone());
+                        episode
+                    };
+
+                    PlayableItem::Episode(episode)
                 }
             };
 
@@ -158,17 +361,24 @@ async fn get_current_playback(
                 is_playing: playing.is_playing,
                 position: playing.progress.unwrap().num_seconds() as u64,
                 device_id: playing.device.clone().id.unwrap(),
+                shuffled: playing.shuffle_state,
+                repeat_status: playing.repeat_state,
             });
 
             let res_playing = CurrentlyPlaying {
                 device: playing.device,
-                track: Track::simplify_track(track_info).await,
+                item: item_info,
                 progress_secs: playing.progress.unwrap().num_seconds() as u32,
                 shuffled: playing.shuffle_state,
                 playing: playing.is_playing,
                 repeat_status: playing.repeat_state,
             };
 
+            locked_state.playback_cache = Some(PlaybackCache {
+                response: res_playing.clone(),
+                last_fetched: Instant::now(),
+            });
+
             let body = serde_json::to_string(&res_playing).unwrap();
 
             Ok(Response::builder()
@@ -178,6 +388,7 @@ async fn get_current_playback(
         }
         Ok(None) => {
             locked_state.playback_status = None;
+            locked_state.playback_cache = None;
 
             Ok(Response::builder()
                 .header("Content-Type", "application/json")
@@ -186,6 +397,7 @@ async fn get_current_playback(
         }
         Err(err) => {
             locked_state.playback_status = None;
+            locked_state.playback_cache = None;
 
             Err(format!("Error with getting playback, {}", err))
         }
@@ -196,10 +408,7 @@ async fn toggle_playback(
     Query(params): Query<AuthQueryParam>,
     state: Extension<Arc<Mutex<SpotifyState>>>,
 ) -> Result<Response<Body>, String> {
-    let locked_token = AUTH_TOKEN.lock().await;
-    let auth_token = params.auth_token;
-
-    if locked_token.as_str() != auth_token {
+    if !is_authorized(&params.auth_token).await {
         return Ok(Response::builder()
             .status(400)
             .header("Content-Type", "application/json")
@@ -209,7 +418,7 @@ async fn toggle_playback(
 
     update_state(state.clone()).await;
 
-    let locked_state = state.lock().await;
+    let mut locked_state = state.lock().await;
 
     if let Some(mut playback) = locked_state.playback_status.clone() {
         if playback.is_playing {
@@ -218,6 +427,7 @@ async fn toggle_playback(
                 .pause_playback(Some(playback.device_id.as_str()))
                 .await;
             playback.is_playing = false;
+            locked_state.playback_cache = None;
 
             return Ok(Response::builder()
                 .status(200)
@@ -234,6 +444,7 @@ async fn toggle_playback(
                 .await;
 
             playback.is_playing = false;
+            locked_state.playback_cache = None;
 
             return Ok(Response::builder()
                 .status(200)
@@ -250,9 +461,7 @@ async fn next_track(
     Query(params): Query<AuthQueryParam>,
     state: Extension<Arc<Mutex<SpotifyState>>>,
 ) -> Result<Response<Body>, String> {
-    let locked_token = AUTH_TOKEN.lock().await;
-    let auth_token = params.auth_token;
-    if locked_token.as_str() != auth_token {
+    if !is_authorized(&params.auth_token).await {
         return Ok(Response::builder()
             .status(400)
             .header("Content-Type", "application/json")
@@ -262,12 +471,13 @@ async fn next_track(
 
     update_state(state.clone()).await;
 
-    let locked_state = state.lock().await;
-    let device_id = locked_state.clone().playback_status.unwrap().device_id;
+    let mut locked_state = state.lock().await;
+    let device_id = locked_state.playback_status.clone().unwrap().device_id;
     let _ = locked_state
         .spotify
         .next_track(Some(device_id.as_str()))
         .await;
+    locked_state.playback_cache = None;
 
     return Ok(Response::builder()
         .status(200)
@@ -282,9 +492,7 @@ async fn previous_track(
     Query(params): Query<AuthQueryParam>,
     state: Extension<Arc<Mutex<SpotifyState>>>,
 ) -> Result<Response<Body>, String> {
-    let locked_token = AUTH_TOKEN.lock().await;
-    let auth_token = params.auth_token;
-    if locked_token.as_str() != auth_token {
+    if !is_authorized(&params.auth_token).await {
         return Ok(Response::builder()
             .status(400)
             .header("Content-Type", "application/json")
@@ -294,12 +502,13 @@ async fn previous_track(
 
     update_state(state.clone()).await;
 
-    let locked_state = state.lock().await;
-    let device_id = locked_state.clone().playback_status.unwrap().device_id;
+    let mut locked_state = state.lock().await;
+    let device_id = locked_state.playback_status.clone().unwrap().device_id;
     let _ = locked_state
         .spotify
         .previous_track(Some(device_id.as_str()))
         .await;
+    locked_state.playback_cache = None;
 
     return Ok(Response::builder()
         .status(200)
@@ -314,9 +523,7 @@ async fn restart_track(
     Query(params): Query<AuthQueryParam>,
     state: Extension<Arc<Mutex<SpotifyState>>>,
 ) -> Result<Response<Body>, String> {
-    let locked_token = AUTH_TOKEN.lock().await;
-    let auth_token = params.auth_token;
-    if locked_token.as_str() != auth_token {
+    if !is_authorized(&params.auth_token).await {
         return Ok(Response::builder()
             .status(400)
             .header("Content-Type", "application/json")
@@ -326,12 +533,13 @@ async fn restart_track(
 
     update_state(state.clone()).await;
 
-    let locked_state = state.lock().await;
-    let device_id = locked_state.clone().playback_status.unwrap().device_id;
+    let mut locked_state = state.lock().await;
+    let device_id = locked_state.playback_status.clone().unwrap().device_id;
     let _ = locked_state
         .spotify
         .seek_track(Duration::seconds(0), Some(device_id.as_str()))
         .await;
+    locked_state.playback_cache = None;
 
     return Ok(Response::builder()
         .status(200)
@@ -342,6 +550,274 @@ async fn restart_track(
         .unwrap());
 }
 
+async fn issue_token(Query(params): Query<AuthQueryParam>) -> Response<Body> {
+    if AUTH_TOKEN.lock().await.as_str() != params.auth_token {
+        return Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new("{\"message\": \"invalid token\"}".to_string()))
+            .unwrap();
+    }
+
+    let scoped_token = Uuid::new_v4().to_string();
+    let expires_at = Instant::now() + *SCOPED_TOKEN_TTL;
+
+    let mut scoped_tokens = SCOPED_TOKENS.lock().await;
+    scoped_tokens.retain(|_, expires_at| *expires_at > Instant::now());
+    scoped_tokens.insert(scoped_token.clone(), expires_at);
+
+    let body = serde_json::to_string(&serde_json::json!({ "token": scoped_token })).unwrap();
+
+    Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn get_queue(state: Extension<Arc<Mutex<SpotifyState>>>) -> Result<Response<Body>, String> {
+    let locked_state = state.lock().await;
+
+    ensure_fresh_token(&locked_state.spotify).await;
+
+    let queue = locked_state
+        .spotify
+        .current_user_queue()
+        .await
+        .map_err(|err| format!("Error with getting queue, {}", err))?;
+
+    let mut tracks = Vec::new();
+    for item in queue.queue {
+        if let rspotify::model::PlayableItem::Track(full_track) = item {
+            tracks.push(Track::simplify_track(full_track).await);
+        }
+    }
+
+    let body = serde_json::to_string(&tracks).unwrap();
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+// Spotify caps list responses at 50 items per page, so this pages through with increasing
+// `offset` until a short page tells us there's nothing left, and hands the client one full list.
+const PLAYLIST_PAGE_LIMIT: u32 = 50;
+
+async fn get_playlist(
+    Query(params): Query<PlaylistQueryParam>,
+    state: Extension<Arc<Mutex<SpotifyState>>>,
+) -> Result<Response<Body>, String> {
+    let spotify = {
+        let locked_state = state.lock().await;
+        ensure_fresh_token(&locked_state.spotify).await;
+        locked_state.spotify.clone()
+    };
+
+    let playlist_id =
+        PlaylistId::from_id(params.id).map_err(|err| format!("invalid playlist id, {}", err))?;
+
+    let mut tracks = Vec::new();
+    let mut offset = 0u32;
+
+    loop {
+        let page = spotify
+            .playlist_items_manual(
+                playlist_id.clone(),
+                None,
+                None,
+                Some(PLAYLIST_PAGE_LIMIT),
+                Some(offset),
+            )
+            .await
+            .map_err(|err| format!("Error with getting playlist, {}", err))?;
+
+        let page_len = page.items.len() as u32;
+
+        for item in page.items {
+            if let Some(rspotify::model::PlayableItem::Track(full_track)) = item.track {
+                tracks.push(Track::simplify_track(full_track).await);
+            }
+        }
+
+        if page_len < PLAYLIST_PAGE_LIMIT {
+            break;
+        }
+
+        offset += PLAYLIST_PAGE_LIMIT;
+    }
+
+    let body = serde_json::to_string(&tracks).unwrap();
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+async fn set_volume(
+    Query(params): Query<SetVolumeQueryParam>,
+    state: Extension<Arc<Mutex<SpotifyState>>>,
+) -> Result<Response<Body>, String> {
+    if !is_authorized(&params.auth_token).await {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new("{\"message\": \"invalid token\"}".to_string()))
+            .unwrap());
+    }
+
+    update_state(state.clone()).await;
+
+    let mut locked_state = state.lock().await;
+    let Some(playback) = locked_state.playback_status.clone() else {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new(
+                "{\"message\": \"no active playback\"}".to_string(),
+            ))
+            .unwrap());
+    };
+    let _ = locked_state
+        .spotify
+        .volume(params.volume.min(100), Some(playback.device_id.as_str()))
+        .await;
+    locked_state.playback_cache = None;
+
+    Ok(Response::builder()
+        .status(200)
+        .header("Content-Type", "application/json")
+        .body(Body::from("{\"message\": \"volume updated\"}".to_string()))
+        .unwrap())
+}
+
+async fn seek(
+    Query(params): Query<SeekQueryParam>,
+    state: Extension<Arc<Mutex<SpotifyState>>>,
+) -> Result<Response<Body>, String> {
+    if !is_authorized(&params.auth_token).await {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new("{\"message\": \"invalid token\"}".to_string()))
+            .unwrap());
+    }
+
+    update_state(state.clone()).await;
+
+    let mut locked_state = state.lock().await;
+    let Some(playback) = locked_state.playback_status.clone() else {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new(
+                "{\"message\": \"no active playback\"}".to_string(),
+            ))
+            .unwrap());
+    };
+    let _ = locked_state
+        .spotify
+        .seek_track(
+            Duration::seconds(params.position_secs as i64),
+            Some(playback.device_id.as_str()),
+        )
+        .await;
+    locked_state.playback_cache = None;
+
+    Ok(Response::builder()
+        .status(200)
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            "{\"message\": \"seeked to position\"}".to_string(),
+        ))
+        .unwrap())
+}
+
+async fn toggle_shuffle(
+    Query(params): Query<AuthQueryParam>,
+    state: Extension<Arc<Mutex<SpotifyState>>>,
+) -> Result<Response<Body>, String> {
+    if !is_authorized(&params.auth_token).await {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new("{\"message\": \"invalid token\"}".to_string()))
+            .unwrap());
+    }
+
+    update_state(state.clone()).await;
+
+    let mut locked_state = state.lock().await;
+    let Some(playback) = locked_state.playback_status.clone() else {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new(
+                "{\"message\": \"no active playback\"}".to_string(),
+            ))
+            .unwrap());
+    };
+    let _ = locked_state
+        .spotify
+        .shuffle(!playback.shuffled, Some(playback.device_id.as_str()))
+        .await;
+    locked_state.playback_cache = None;
+
+    Ok(Response::builder()
+        .status(200)
+        .header("Content-Type", "application/json")
+        .body(Body::from("{\"message\": \"shuffle toggled\"}".to_string()))
+        .unwrap())
+}
+
+// Cycles Off -> Context -> Track -> Off, mirroring the repeat button in the Spotify clients.
+fn next_repeat_state(current: RepeatState) -> RepeatState {
+    match current {
+        RepeatState::Off => RepeatState::Context,
+        RepeatState::Context => RepeatState::Track,
+        RepeatState::Track => RepeatState::Off,
+    }
+}
+
+async fn cycle_repeat(
+    Query(params): Query<AuthQueryParam>,
+    state: Extension<Arc<Mutex<SpotifyState>>>,
+) -> Result<Response<Body>, String> {
+    if !is_authorized(&params.auth_token).await {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new("{\"message\": \"invalid token\"}".to_string()))
+            .unwrap());
+    }
+
+    update_state(state.clone()).await;
+
+    let mut locked_state = state.lock().await;
+    let Some(playback) = locked_state.playback_status.clone() else {
+        return Ok(Response::builder()
+            .status(400)
+            .header("Content-Type", "application/json")
+            .body(Body::new(
+                "{\"message\": \"no active playback\"}".to_string(),
+            ))
+            .unwrap());
+    };
+    let new_repeat_state = next_repeat_state(playback.repeat_status);
+    let _ = locked_state
+        .spotify
+        .repeat(new_repeat_state, Some(playback.device_id.as_str()))
+        .await;
+    locked_state.playback_cache = None;
+
+    Ok(Response::builder()
+        .status(200)
+        .header("Content-Type", "application/json")
+        .body(Body::from("{\"message\": \"repeat mode cycled\"}".to_string()))
+        .unwrap())
+}
+
 async fn get_resized_image(Query(image_param): Query<ImageQueryParam>) -> Response<Body> {
     let url = image_param.image_url;
 
@@ -427,6 +903,9 @@ async fn main() {
     let shared_state = Arc::new(Mutex::new(SpotifyState {
         spotify,
         playback_status: None,
+        playback_cache: None,
+        track_cache: HashMap::new(),
+        episode_cache: HashMap::new(),
     }));
 
     update_state(Extension(shared_state.clone())).await;
@@ -437,11 +916,64 @@ async fn main() {
         .route("/next_track", get(next_track))
         .route("/previous_track", get(previous_track))
         .route("/restart_track", get(restart_track))
+        .route("/issue_token", get(issue_token))
+        .route("/queue", get(get_queue))
+        .route("/playlist", get(get_playlist))
+        .route("/set_volume", get(set_volume))
+        .route("/seek", get(seek))
+        .route("/toggle_shuffle", get(toggle_shuffle))
+        .route("/cycle_repeat", get(cycle_repeat))
         .route("/get_resized_image", get(get_resized_image))
+        .layer(cors_layer())
         .layer(Extension(shared_state));
 
-    // let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
-    //
-    // axum::serve(listener, app).await.unwrap();
-    run(app).await;
+    // Lambda is the default target, but setting LOCAL_BIND_ADDR (e.g. for local development or a
+    // standalone deployment) switches to binding a real TCP listener instead, optionally over TLS.
+    match std::env::var("LOCAL_BIND_ADDR") {
+        Ok(bind_addr) => serve_locally(app, bind_addr).await,
+        Err(_) => run(app).await.unwrap(),
+    }
+}
+
+// Allowed origins come from a comma-separated `CORS_ALLOWED_ORIGINS` env var; with none set, no
+// browser origin is allowed, same as today's Lambda-only, CORS-less behavior.
+fn cors_layer() -> CorsLayer {
+    let allowed_origins: Vec<HeaderValue> = dotenv::var("CORS_ALLOWED_ORIGINS")
+        .map(|origins| {
+            origins
+                .split(',')
+                .map(|origin| origin.trim().parse().expect("invalid CORS_ALLOWED_ORIGINS entry"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    CorsLayer::new()
+        .allow_origin(allowed_origins)
+        .allow_methods([axum::http::Method::GET])
+}
+
+async fn serve_locally(app: Router, bind_addr: String) {
+    let socket_addr: SocketAddr = bind_addr.parse().expect("invalid LOCAL_BIND_ADDR");
+
+    let tls_paths = (
+        std::env::var("TLS_CERT_PATH"),
+        std::env::var("TLS_KEY_PATH"),
+    );
+
+    match tls_paths {
+        (Ok(cert_path), Ok(key_path)) => {
+            let tls_config = RustlsConfig::from_pem_file(cert_path, key_path)
+                .await
+                .expect("invalid TLS cert/key");
+
+            axum_server::bind_rustls(socket_addr, tls_config)
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        }
+        _ => {
+            let listener = tokio::net::TcpListener::bind(socket_addr).await.unwrap();
+            axum::serve(listener, app).await.unwrap();
+        }
+    }
 }